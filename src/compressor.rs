@@ -17,9 +17,108 @@
 //! ```
 
 use std::error::Error;
+use std::num::NonZeroU32;
 
-use image::imageops::FilterType;
-use mozjpeg::{ColorSpace, Compress, ScanMode};
+use fast_image_resize as fr;
+use image::codecs::png::PngEncoder;
+use image::{DynamicImage, ImageEncoder};
+use mozjpeg::{ColorSpace, Compress, Marker, ScanMode};
+
+/// Output codec used when encoding the compressed image.
+///
+/// `Auto` inspects the source image and picks [`OutputFormat::Png`] when it has an alpha
+/// channel (since JPEG/WebP-lossy would otherwise flatten transparency) or when it otherwise
+/// looks lossless-friendly (see [`looks_lossless_friendly`] — flat-color art, screenshots,
+/// and similar low-color-count images compress better losslessly than as JPEG), falling back
+/// to [`OutputFormat::Jpeg`] for everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Encode with mozjpeg. This is the historical, and still default, behavior.
+    #[default]
+    Jpeg,
+    /// Encode as PNG, optimized losslessly with oxipng.
+    Png,
+    /// Encode as WebP via `libwebp-sys`.
+    WebP,
+    /// Encode as AVIF via `libavif`.
+    Avif,
+    /// Pick [`OutputFormat::Png`] or [`OutputFormat::Jpeg`] based on the source image.
+    Auto,
+}
+
+/// Chroma subsampling mode for the JPEG encoder.
+///
+/// 4:2:0 (`S420`) halves the chroma resolution in both directions for smaller files and is
+/// the right default for photographic content. 4:2:2 (`S422`) only halves it horizontally.
+/// 4:4:4 (`S444`) keeps full chroma resolution, which preserves sharp color edges in
+/// high-detail images and screenshots at the cost of a larger file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Subsampling {
+    /// Pick 4:2:0 to preserve the encoder's historical behavior.
+    #[default]
+    Auto,
+    /// 4:4:4, no chroma subsampling.
+    S444,
+    /// 4:2:2, chroma halved horizontally.
+    S422,
+    /// 4:2:0, chroma halved in both directions.
+    S420,
+}
+
+impl Subsampling {
+    /// Horizontal/vertical sampling factors for the luma component; chroma components
+    /// always use `(1, 1)`.
+    fn luma_sampling_factors(&self) -> (u8, u8) {
+        match self {
+            Subsampling::Auto | Subsampling::S420 => (2, 2),
+            Subsampling::S422 => (2, 1),
+            Subsampling::S444 => (1, 1),
+        }
+    }
+}
+
+/// How a [`Factor`] determines the resized dimensions of the compressed image.
+///
+/// `Ratio` is the original behavior: both dimensions are multiplied by the same factor.
+/// `LongestEdge` instead targets a maximum size for the longest side, deriving the other
+/// dimension from it so the aspect ratio is preserved; images already smaller than the
+/// target are left untouched.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum ResizeMode {
+    /// Multiply both dimensions by this ratio. Values range from 0 to 1 in float.
+    Ratio(f32),
+    /// Resize so the longest edge is at most this many pixels, preserving aspect ratio.
+    LongestEdge(u32),
+}
+
+/// Resizer backend used by [`Compressor`] to downscale the source image.
+///
+/// All variants run through a SIMD convolution resizer (`fast_image_resize`, using
+/// SSE4.1/AVX2/NEON where available) rather than the scalar resizer in the `image` crate,
+/// which is the single slowest step of the folder-compression path for large batches.
+/// `Triangle` maps to `fast_image_resize`'s bilinear filter, the linear-filter equivalent of
+/// `image`'s `FilterType::Triangle`, so it is a drop-in replacement for the previous default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeAlgorithm {
+    /// Linear (tent) filter. Matches the historical `image::imageops::FilterType::Triangle`
+    /// behavior, just SIMD-accelerated.
+    #[default]
+    Triangle,
+    /// Lanczos resampling with a 3-lobe window. Sharper than `Triangle`, more expensive.
+    Lanczos3,
+    /// Catmull-Rom cubic filter. A middle ground between `Triangle` and `Lanczos3`.
+    CatmullRom,
+}
+
+impl ResizeAlgorithm {
+    fn as_fr_filter_type(&self) -> fr::FilterType {
+        match self {
+            ResizeAlgorithm::Triangle => fr::FilterType::Bilinear,
+            ResizeAlgorithm::Lanczos3 => fr::FilterType::Lanczos3,
+            ResizeAlgorithm::CatmullRom => fr::FilterType::CatmullRom,
+        }
+    }
+}
 
 /// Factor struct that used for setting quality and resize ratio in the new image.
 ///
@@ -40,6 +139,12 @@ pub struct Factor {
     /// Ratio for resize the new compressed image.
     /// Values range from 0 to 1 in float.
     size_ratio: f32,
+
+    /// How the resized dimensions are computed. Defaults to `ResizeMode::Ratio(size_ratio)`.
+    resize_mode: ResizeMode,
+
+    /// Chroma subsampling used by the JPEG encoder. Defaults to `Subsampling::Auto`.
+    subsampling: Subsampling,
 }
 
 impl Factor {
@@ -58,6 +163,8 @@ impl Factor {
             Self {
                 quality,
                 size_ratio,
+                resize_mode: ResizeMode::Ratio(size_ratio),
+                subsampling: Subsampling::default(),
             }
         } else {
             panic!("Wrong Factor argument!");
@@ -73,6 +180,26 @@ impl Factor {
     pub fn size_ratio(&self) -> f32 {
         self.size_ratio
     }
+
+    /// Getter for the active [`ResizeMode`].
+    pub fn resize_mode(&self) -> ResizeMode {
+        self.resize_mode
+    }
+
+    /// Switch to resizing against a target longest-edge size instead of the fixed ratio.
+    pub fn set_resize_mode(&mut self, resize_mode: ResizeMode) {
+        self.resize_mode = resize_mode;
+    }
+
+    /// Getter for the active [`Subsampling`] mode.
+    pub fn subsampling(&self) -> Subsampling {
+        self.subsampling
+    }
+
+    /// Set the chroma subsampling mode used by the JPEG encoder.
+    pub fn set_subsampling(&mut self, subsampling: Subsampling) {
+        self.subsampling = subsampling;
+    }
 }
 
 impl Default for Factor {
@@ -80,6 +207,8 @@ impl Default for Factor {
         Self {
             quality: 80.,
             size_ratio: 0.8,
+            resize_mode: ResizeMode::Ratio(0.8),
+            subsampling: Subsampling::default(),
         }
     }
 }
@@ -89,6 +218,11 @@ impl Default for Factor {
 pub struct Compressor {
     factor: Factor,
     image: image::DynamicImage,
+    format: OutputFormat,
+    exif: Option<Vec<u8>>,
+    icc_profile: Option<Vec<u8>>,
+    preserve_metadata: bool,
+    resize_algorithm: ResizeAlgorithm,
 }
 
 impl Compressor {
@@ -112,17 +246,82 @@ impl Compressor {
         Compressor {
             factor: Factor::default(),
             image,
+            format: OutputFormat::default(),
+            exif: None,
+            icc_profile: None,
+            preserve_metadata: false,
+            resize_algorithm: ResizeAlgorithm::default(),
         }
     }
 
+    /// Create a new compressor from raw encoded image bytes, carrying over the EXIF (APP1)
+    /// and ICC profile (APP2) segments found in `data` so they can be written back into the
+    /// compressed output when [`Compressor::preserve_metadata`] is enabled.
+    ///
+    /// # Examples
+    /// ```
+    /// use image_compressor::compressor::Compressor;
+    ///
+    /// let data = include_bytes!("../tests/test.jpg");
+    /// let compressor = Compressor::new_from_bytes(data).expect("panic");
+    /// ```
+    pub fn new_from_bytes(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let image = image::load_from_memory(data)?;
+        Ok(Compressor {
+            factor: Factor::default(),
+            image,
+            format: OutputFormat::default(),
+            exif: extract_app1_exif(data),
+            icc_profile: extract_icc_profile(data),
+            preserve_metadata: false,
+            resize_algorithm: ResizeAlgorithm::default(),
+        })
+    }
+
+    /// Toggle whether EXIF and ICC profile metadata (gathered via
+    /// [`Compressor::new_from_bytes`]) is written back into the compressed JPEG output.
+    /// Defaults to `false`.
+    pub fn preserve_metadata(&mut self, preserve: bool) {
+        self.preserve_metadata = preserve;
+    }
+
     /// Set factor for the new compressed image.
     pub fn set_factor(&mut self, factor: Factor) {
         self.factor = factor;
     }
 
+    /// Set the output codec used by [`Compressor::compress_image`].
+    pub fn set_format(&mut self, format: OutputFormat) {
+        self.format = format;
+    }
+
+    /// Set the resizer backend/filter used when downscaling the source image.
+    pub fn set_resize_algorithm(&mut self, resize_algorithm: ResizeAlgorithm) {
+        self.resize_algorithm = resize_algorithm;
+    }
+
+    /// Resolve `OutputFormat::Auto` against the source image, leaving every other variant
+    /// untouched.
+    ///
+    /// Picks PNG when the image has an alpha channel, or when it otherwise looks
+    /// lossless-friendly per [`looks_lossless_friendly`] (flat-color art, screenshots, and
+    /// similar low-color-count images); falls back to JPEG for everything else.
+    fn resolve_format(&self) -> OutputFormat {
+        match self.format {
+            OutputFormat::Auto => {
+                if self.image.color().has_alpha() || looks_lossless_friendly(&self.image) {
+                    OutputFormat::Png
+                } else {
+                    OutputFormat::Jpeg
+                }
+            }
+            other => other,
+        }
+    }
+
     fn compress(
         &self,
-        resized_img_data: Vec<u8>,
+        resized_img_data: &[u8],
         target_width: usize,
         target_height: usize,
         quality: f32,
@@ -133,10 +332,32 @@ impl Compressor {
 
         comp.set_size(target_width, target_height);
 
+        let (luma_h, luma_v) = self.factor.subsampling().luma_sampling_factors();
+        {
+            let components = comp.components_mut();
+            components[0].h_samp_factor = luma_h;
+            components[0].v_samp_factor = luma_v;
+            components[1].h_samp_factor = 1;
+            components[1].v_samp_factor = 1;
+            components[2].h_samp_factor = 1;
+            components[2].v_samp_factor = 1;
+        }
+
         comp.set_mem_dest();
         comp.set_optimize_scans(true);
         comp.start_compress();
 
+        if self.preserve_metadata {
+            if let Some(exif) = &self.exif {
+                comp.write_marker(Marker::APP(1), exif);
+            }
+            if let Some(icc_profile) = &self.icc_profile {
+                for marker in build_icc_markers(icc_profile) {
+                    comp.write_marker(Marker::APP(2), &marker);
+                }
+            }
+        }
+
         let mut line = 0;
         loop {
             if line > target_height - 1 {
@@ -155,27 +376,159 @@ impl Compressor {
         Ok(compressed)
     }
 
+    /// Resolve the factor's [`ResizeMode`] into a concrete ratio for the source image.
+    ///
+    /// `ResizeMode::Ratio` passes through unchanged. `ResizeMode::LongestEdge` computes
+    /// `target / max(width, height)`, clamped to `1.0` so images already smaller than the
+    /// target are left untouched.
+    fn resolve_resize_ratio(&self) -> f32 {
+        match self.factor.resize_mode() {
+            ResizeMode::Ratio(ratio) => ratio,
+            ResizeMode::LongestEdge(target) => {
+                let longest_edge = self.image.width().max(self.image.height());
+                (target as f32 / longest_edge as f32).min(1.0)
+            }
+        }
+    }
+
+    /// Downscale the source image through the SIMD convolution resizer selected by
+    /// [`Compressor::set_resize_algorithm`], operating on a raw `pixel_type` buffer.
+    ///
+    /// Clamps the target width/height to at least 1px: a ratio that would round a dimension
+    /// down to zero otherwise produces a divide/NaN while precomputing the convolution weights.
+    fn fast_resize(
+        &self,
+        resize_ratio: f32,
+        pixel_type: fr::PixelType,
+        src_buf: Vec<u8>,
+    ) -> Result<(Vec<u8>, u32, u32), Box<dyn Error>> {
+        let img = &self.image;
+        let src_width = NonZeroU32::new(img.width()).ok_or("source image has zero width")?;
+        let src_height = NonZeroU32::new(img.height()).ok_or("source image has zero height")?;
+
+        let target_width = ((img.width() as f32 * resize_ratio) as u32).max(1);
+        let target_height = ((img.height() as f32 * resize_ratio) as u32).max(1);
+        let dst_width = NonZeroU32::new(target_width).unwrap();
+        let dst_height = NonZeroU32::new(target_height).unwrap();
+
+        let src_image = fr::Image::from_vec_u8(src_width, src_height, src_buf, pixel_type)?;
+        let mut dst_image = fr::Image::new(dst_width, dst_height, pixel_type);
+
+        let mut resizer = fr::Resizer::new(fr::ResizeAlg::Convolution(
+            self.resize_algorithm.as_fr_filter_type(),
+        ));
+        resizer.resize(&src_image.view(), &mut dst_image.view_mut())?;
+
+        Ok((dst_image.into_vec(), target_width, target_height))
+    }
+
     fn resize(
         &self,
         resize_ratio: f32,
     ) -> Result<(Vec<u8>, usize, usize), Box<dyn Error>> {
-        let img = &self.image;
-        let width = img.width() as usize;
-        let height = img.height() as usize;
+        let (buf, target_width, target_height) =
+            self.fast_resize(resize_ratio, fr::PixelType::U8x3, self.image.to_rgb8().into_vec())?;
 
-        let width = width as f32 * resize_ratio;
-        let height = height as f32 * resize_ratio;
+        Ok((buf, target_width as usize, target_height as usize))
+    }
 
-        let resized_img = img.resize(width as u32, height as u32, FilterType::Triangle);
+    /// Resize the source image and return it as a [`DynamicImage`], preserving the alpha
+    /// channel (if any) for the non-JPEG encoders. Runs through the same SIMD resizer as
+    /// [`Compressor::resize`].
+    fn resize_dynamic(&self, resize_ratio: f32) -> Result<DynamicImage, Box<dyn Error>> {
+        if self.image.color().has_alpha() {
+            let (buf, width, height) = self.fast_resize(
+                resize_ratio,
+                fr::PixelType::U8x4,
+                self.image.to_rgba8().into_vec(),
+            )?;
+            let resized = image::RgbaImage::from_raw(width, height, buf)
+                .ok_or("failed to reconstruct resized RGBA image")?;
+            Ok(DynamicImage::from(resized))
+        } else {
+            let (buf, width, height) = self.fast_resize(
+                resize_ratio,
+                fr::PixelType::U8x3,
+                self.image.to_rgb8().into_vec(),
+            )?;
+            let resized = image::RgbImage::from_raw(width, height, buf)
+                .ok_or("failed to reconstruct resized RGB image")?;
+            Ok(DynamicImage::from(resized))
+        }
+    }
 
-        let resized_width = resized_img.width() as usize;
-        let resized_height = resized_img.height() as usize;
+    fn compress_png(
+        &self,
+        resized_img: &DynamicImage,
+        quality: f32,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.compress_png_at_preset(resized_img, quality_to_oxipng_preset(quality))
+    }
 
-        Ok((
-            resized_img.into_rgb8().into_vec(),
-            resized_width,
-            resized_height,
-        ))
+    /// Encode `resized_img` as PNG and run it through oxipng at a specific preset, bypassing
+    /// the `quality` → preset mapping. Lets callers (e.g. [`Compressor::compress_to_size`])
+    /// cache by preset instead of re-running the expensive oxipng pass for every distinct
+    /// `quality` that maps to the same one of the 7 discrete presets.
+    fn compress_png_at_preset(
+        &self,
+        resized_img: &DynamicImage,
+        preset: u8,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut raw_png = Vec::new();
+        PngEncoder::new(&mut raw_png).write_image(
+            resized_img.as_bytes(),
+            resized_img.width(),
+            resized_img.height(),
+            resized_img.color(),
+        )?;
+
+        let optimized = oxipng::optimize_from_memory(&raw_png, &oxipng::Options::from_preset(preset))?;
+        Ok(optimized)
+    }
+
+    fn compress_webp(
+        &self,
+        resized_img: &DynamicImage,
+        quality: f32,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let rgba = resized_img.to_rgba8();
+        let (width, height) = (rgba.width(), rgba.height());
+
+        let encoded = unsafe {
+            let mut out: *mut u8 = std::ptr::null_mut();
+            let len = libwebp_sys::WebPEncodeRGBA(
+                rgba.as_raw().as_ptr(),
+                width as i32,
+                height as i32,
+                width as i32 * 4,
+                quality,
+                &mut out,
+            );
+            if out.is_null() || len == 0 {
+                return Err("WebPEncodeRGBA failed".into());
+            }
+            let data = std::slice::from_raw_parts(out, len).to_vec();
+            libwebp_sys::WebPFree(out as *mut std::ffi::c_void);
+            data
+        };
+
+        Ok(encoded)
+    }
+
+    fn compress_avif(
+        &self,
+        resized_img: &DynamicImage,
+        quality: f32,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let rgba = resized_img.to_rgba8();
+        let quality = quality.round() as u8;
+
+        let encoded = libavif::AvifEncoder::new()
+            .quality(quality)
+            .encode_rgba(rgba.width(), rgba.height(), rgba.as_raw())
+            .map_err(|e| e.to_string())?;
+
+        Ok(encoded)
     }
 
     /// Compress a file.
@@ -188,6 +541,9 @@ impl Compressor {
     ///
     /// If the flag to delete the original is true, the function delete the original file.
     ///
+    /// The output codec is controlled by [`Compressor::set_format`]; it defaults to
+    /// [`OutputFormat::Jpeg`], matching the historical behavior of this function.
+    ///
     /// # Examples
     /// ```
     /// use std::path::PathBuf;
@@ -201,15 +557,312 @@ impl Compressor {
     /// compressor.compress_image().expect("panic");
     /// ```
     pub fn compress_image(&self) -> Result<Vec<u8>, Box<dyn Error>> {
-        let (resized_img_data, target_width, target_height) =
-            self.resize(self.factor.size_ratio())?;
-        let compressed_img_data = self.compress(
-            resized_img_data,
-            target_width,
-            target_height,
-            self.factor.quality(),
-        )?;
+        match self.resolve_format() {
+            OutputFormat::Jpeg => {
+                let (resized_img_data, target_width, target_height) =
+                    self.resize(self.resolve_resize_ratio())?;
+                self.compress(
+                    &resized_img_data,
+                    target_width,
+                    target_height,
+                    self.factor.quality(),
+                )
+            }
+            OutputFormat::Png => {
+                let resized_img = self.resize_dynamic(self.resolve_resize_ratio())?;
+                self.compress_png(&resized_img, self.factor.quality())
+            }
+            OutputFormat::WebP => {
+                let resized_img = self.resize_dynamic(self.resolve_resize_ratio())?;
+                self.compress_webp(&resized_img, self.factor.quality())
+            }
+            OutputFormat::Avif => {
+                let resized_img = self.resize_dynamic(self.resolve_resize_ratio())?;
+                self.compress_avif(&resized_img, self.factor.quality())
+            }
+            OutputFormat::Auto => unreachable!("resolve_format never returns Auto"),
+        }
+    }
+
+    /// Binary search over the quality range (1.0 to 100.0, ~10 iterations) for the highest
+    /// quality whose `compress_at` output still fits within `target_bytes`. Falls back to the
+    /// lowest-quality candidate tried when nothing fits.
+    fn search_quality_for_size(
+        &self,
+        target_bytes: usize,
+        mut compress_at: impl FnMut(f32) -> Result<Vec<u8>, Box<dyn Error>>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut low = 1.0_f32;
+        let mut high = 100.0_f32;
+        let mut best_fit: Option<Vec<u8>> = None;
+        let mut lowest_quality_candidate: Option<Vec<u8>> = None;
+
+        for _ in 0..10 {
+            let mid = (low + high) / 2.0;
+            let candidate = compress_at(mid)?;
+
+            if candidate.len() <= target_bytes {
+                best_fit = Some(candidate);
+                low = mid;
+            } else {
+                lowest_quality_candidate = Some(candidate);
+                high = mid;
+            }
+        }
+
+        best_fit
+            .or(lowest_quality_candidate)
+            .ok_or_else(|| "compress_to_size failed to produce any candidate".into())
+    }
+
+    /// Compress the image so that the resulting buffer is at most `target_bytes` long.
+    ///
+    /// The resize step (in whichever form the active [`OutputFormat`] needs, resolved via
+    /// [`Compressor::resolve_format`] exactly like [`Compressor::compress_image`]) is applied
+    /// once up front, then this function searches for the highest quality that still keeps
+    /// the compressed output within `target_bytes` using a binary search over the quality
+    /// range (1.0 to 100.0). Each search step only re-encodes the already-resized image, so
+    /// the resize itself isn't repeated.
+    ///
+    /// If no quality fits within `target_bytes`, the smallest-quality candidate is returned
+    /// instead, since that is the closest the compressor can get.
+    ///
+    /// # Examples
+    /// ```
+    /// use image_compressor::compressor::Compressor;
+    ///
+    /// let compressor = Compressor::new(image::load_from_memory(include_bytes!("../tests/test.jpg")).unwrap());
+    /// let compressed = compressor.compress_to_size(200_000).expect("panic");
+    /// ```
+    pub fn compress_to_size(&self, target_bytes: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self.resolve_format() {
+            OutputFormat::Jpeg => {
+                let (resized_img_data, target_width, target_height) =
+                    self.resize(self.resolve_resize_ratio())?;
+                self.search_quality_for_size(target_bytes, |quality| {
+                    self.compress(&resized_img_data, target_width, target_height, quality)
+                })
+            }
+            OutputFormat::Png => {
+                let resized_img = self.resize_dynamic(self.resolve_resize_ratio())?;
+                // `quality` only maps to 7 discrete oxipng presets, but the binary search
+                // runs a fixed number of iterations over a continuous range, so distinct
+                // search steps routinely land on a preset already tried. Cache by preset to
+                // avoid paying for the same full oxipng pass more than once.
+                let mut by_preset: std::collections::HashMap<u8, Vec<u8>> =
+                    std::collections::HashMap::new();
+                self.search_quality_for_size(target_bytes, move |quality| {
+                    let preset = quality_to_oxipng_preset(quality);
+                    if let Some(cached) = by_preset.get(&preset) {
+                        return Ok(cached.clone());
+                    }
+                    let encoded = self.compress_png_at_preset(&resized_img, preset)?;
+                    by_preset.insert(preset, encoded.clone());
+                    Ok(encoded)
+                })
+            }
+            OutputFormat::WebP => {
+                let resized_img = self.resize_dynamic(self.resolve_resize_ratio())?;
+                self.search_quality_for_size(target_bytes, |quality| {
+                    self.compress_webp(&resized_img, quality)
+                })
+            }
+            OutputFormat::Avif => {
+                let resized_img = self.resize_dynamic(self.resolve_resize_ratio())?;
+                self.search_quality_for_size(target_bytes, |quality| {
+                    self.compress_avif(&resized_img, quality)
+                })
+            }
+            OutputFormat::Auto => unreachable!("resolve_format never returns Auto"),
+        }
+    }
+}
+
+/// Map a `Factor::quality` value onto one of oxipng's 7 optimization presets (0 =
+/// fastest/least-optimized, 6 = slowest/most optimized). PNG is lossless, so `quality` has no
+/// visual-fidelity knob to drive; this keeps "higher quality" meaning "try harder to shrink
+/// the file" instead.
+fn quality_to_oxipng_preset(quality: f32) -> u8 {
+    ((quality / 100.0) * 6.0).round().clamp(0.0, 6.0) as u8
+}
+
+/// Heuristic used by [`Compressor::resolve_format`] to decide whether an image without an
+/// alpha channel is still better off as lossless PNG than as JPEG: flat-color art and
+/// screenshots tend to use only a handful of distinct colors, where PNG's lossless
+/// compression beats JPEG on both size and fidelity.
+///
+/// Counts distinct RGB colors up to `UNIQUE_COLOR_THRESHOLD`, bailing out early (returning
+/// `false`) as soon as the count exceeds it, so a photographic image doesn't pay for a full
+/// scan.
+fn looks_lossless_friendly(image: &DynamicImage) -> bool {
+    const UNIQUE_COLOR_THRESHOLD: usize = 4096;
+
+    let rgb = image.to_rgb8();
+    let mut colors = std::collections::HashSet::with_capacity(UNIQUE_COLOR_THRESHOLD + 1);
+    for pixel in rgb.pixels() {
+        colors.insert(pixel.0);
+        if colors.len() > UNIQUE_COLOR_THRESHOLD {
+            return false;
+        }
+    }
+    true
+}
+
+/// Walk the APP segments of a JPEG byte stream, returning the payload of each segment
+/// (excluding the marker and length bytes) whose marker byte matches `marker_byte`.
+fn app_segments(data: &[u8], marker_byte: u8) -> Vec<&[u8]> {
+    let mut segments = Vec::new();
+    if data.len() < 2 || data[0] != 0xFF || data[1] != 0xD8 {
+        return segments;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            break;
+        }
+        let marker = data[pos + 1];
+        if marker == 0xD9 || marker == 0xDA {
+            break;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        if len < 2 || pos + 2 + len > data.len() {
+            break;
+        }
+        if marker == marker_byte {
+            segments.push(&data[pos + 4..pos + 2 + len]);
+        }
+        pos += 2 + len;
+    }
+
+    segments
+}
+
+/// Find the first APP1 segment containing an `Exif\0\0`-prefixed payload.
+fn extract_app1_exif(data: &[u8]) -> Option<Vec<u8>> {
+    app_segments(data, 0xE1)
+        .into_iter()
+        .find(|payload| payload.starts_with(b"Exif\0\0"))
+        .map(|payload| payload.to_vec())
+}
+
+/// Reassemble an ICC profile from one or more APP2 `ICC_PROFILE\0` chunks, ordering them by
+/// their embedded sequence number and stripping the 14-byte chunk header.
+fn extract_icc_profile(data: &[u8]) -> Option<Vec<u8>> {
+    let mut chunks: Vec<(u8, &[u8])> = app_segments(data, 0xE2)
+        .into_iter()
+        .filter(|payload| payload.starts_with(b"ICC_PROFILE\0") && payload.len() > 14)
+        .map(|payload| (payload[12], &payload[14..]))
+        .collect();
+
+    if chunks.is_empty() {
+        return None;
+    }
+
+    chunks.sort_by_key(|(seq, _)| *seq);
+    Some(chunks.into_iter().flat_map(|(_, chunk)| chunk).copied().collect())
+}
+
+/// Split a reassembled ICC profile back into ≤64 KB `ICC_PROFILE\0` chunks, each prefixed
+/// with the standard header (`"ICC_PROFILE\0"` + 1-based sequence number + chunk count),
+/// ready to be written as APP2 markers.
+fn build_icc_markers(icc_profile: &[u8]) -> Vec<Vec<u8>> {
+    const MAX_CHUNK_LEN: usize = 65533 - 12 - 2;
+
+    let data_chunks: Vec<&[u8]> = if icc_profile.is_empty() {
+        Vec::new()
+    } else {
+        icc_profile.chunks(MAX_CHUNK_LEN).collect()
+    };
+    let count = data_chunks.len() as u8;
+
+    data_chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut marker = Vec::with_capacity(14 + chunk.len());
+            marker.extend_from_slice(b"ICC_PROFILE\0");
+            marker.push((i + 1) as u8);
+            marker.push(count);
+            marker.extend_from_slice(chunk);
+            marker
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let len = (payload.len() + 2) as u16;
+        let mut segment = vec![0xFF, marker];
+        segment.extend_from_slice(&len.to_be_bytes());
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    fn wrap_in_jpeg(segments: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        for segment in segments {
+            data.extend_from_slice(segment);
+        }
+        data.extend_from_slice(&[0xFF, 0xDA]);
+        data
+    }
+
+    #[test]
+    fn extracts_app1_exif_payload() {
+        let mut exif_payload = b"Exif\0\0".to_vec();
+        exif_payload.extend_from_slice(&[0x4D, 0x4D, 0x00, 0x2A]);
+        let jpeg = wrap_in_jpeg(&[build_segment(0xE1, &exif_payload)]);
+
+        assert_eq!(extract_app1_exif(&jpeg), Some(exif_payload));
+    }
+
+    #[test]
+    fn ignores_app1_segments_without_the_exif_header() {
+        let jpeg = wrap_in_jpeg(&[build_segment(0xE1, b"not exif data")]);
+
+        assert_eq!(extract_app1_exif(&jpeg), None);
+    }
+
+    #[test]
+    fn icc_profile_round_trips_through_build_and_extract() {
+        let icc_profile: Vec<u8> = (0..150_000).map(|i| (i % 256) as u8).collect();
+        let markers = build_icc_markers(&icc_profile);
+        assert!(markers.len() > 1, "fixture should exercise multiple ICC chunks");
+
+        let segments: Vec<Vec<u8>> = markers
+            .iter()
+            .map(|marker| build_segment(0xE2, marker))
+            .collect();
+        let jpeg = wrap_in_jpeg(&segments);
+
+        assert_eq!(extract_icc_profile(&jpeg), Some(icc_profile));
+    }
+
+    #[test]
+    fn icc_profile_reassembles_out_of_order_chunks() {
+        let icc_profile: Vec<u8> = (0..150_000).map(|i| (i % 256) as u8).collect();
+        let mut markers = build_icc_markers(&icc_profile);
+        markers.reverse();
+
+        let segments: Vec<Vec<u8>> = markers
+            .iter()
+            .map(|marker| build_segment(0xE2, marker))
+            .collect();
+        let jpeg = wrap_in_jpeg(&segments);
+
+        assert_eq!(extract_icc_profile(&jpeg), Some(icc_profile));
+    }
+
+    #[test]
+    fn app_segments_stops_scanning_at_start_of_scan() {
+        let mut jpeg = wrap_in_jpeg(&[build_segment(0xE1, b"Exif\0\0seen")]);
+        jpeg.extend_from_slice(&build_segment(0xE1, b"Exif\0\0should not be seen"));
 
-        Ok(compressed_img_data)
+        let segments = app_segments(&jpeg, 0xE1);
+        assert_eq!(segments, vec![b"Exif\0\0seen".as_slice()]);
     }
 }